@@ -0,0 +1,178 @@
+use crate::error::AppError;
+use crate::{ESPLORA_URL, PARALLEL_REQUESTS};
+use bdk_bitcoind_rpc::bitcoincore_rpc::{Auth, Client, RpcApi};
+use bdk_bitcoind_rpc::Emitter;
+use bdk_esplora::esplora_client::{self, AsyncClient};
+use bdk_esplora::EsploraAsyncExt;
+use bdk_sqlx::Store;
+use bdk_wallet::bitcoin::Transaction;
+use bdk_wallet::PersistedWallet;
+use sqlx::Sqlite;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tokio::task::spawn_blocking;
+use tracing::debug;
+
+type SharedWallet = RwLock<PersistedWallet<Store<Sqlite>>>;
+
+// a source of chain data backing the handlers: either the public Esplora HTTP
+// API (default) or a user-run Bitcoin Core node over RPC, selected with the
+// WALLET_CHAIN_BACKEND env var. Both variants can bring the wallet up to tip
+// and broadcast a finalized transaction.
+pub(crate) enum ChainSource {
+    Esplora(AsyncClient),
+    // the RPC client is shared behind an `Arc` so its synchronous, blocking
+    // calls can be moved onto `spawn_blocking` threads
+    Bitcoind(Arc<Client>),
+}
+
+impl ChainSource {
+    // build the chain source from the environment, defaulting to Esplora
+    pub(crate) async fn from_env() -> Result<Self, AppError> {
+        match std::env::var("WALLET_CHAIN_BACKEND").as_deref() {
+            Ok("bitcoind") | Ok("rpc") => {
+                let url = std::env::var("WALLET_BITCOIND_URL")
+                    .unwrap_or_else(|_| "http://127.0.0.1:8332".to_string());
+                // auth is `user:pass`, a cookie file path (a value with no ':'),
+                // or no auth at all when WALLET_BITCOIND_AUTH is unset
+                let auth = match std::env::var("WALLET_BITCOIND_AUTH") {
+                    Ok(auth) => match auth.split_once(':') {
+                        Some((user, pass)) => Auth::UserPass(user.to_string(), pass.to_string()),
+                        None => Auth::CookieFile(auth.into()),
+                    },
+                    Err(_) => Auth::None,
+                };
+                // Client::new performs a blocking handshake, so run it off-runtime
+                let client = spawn_blocking(move || Client::new(&url, auth))
+                    .await
+                    .expect("rpc connect task panicked")?;
+                Ok(ChainSource::Bitcoind(Arc::new(client)))
+            }
+            _ => {
+                let client = esplora_client::Builder::new(ESPLORA_URL).build_async()?;
+                Ok(ChainSource::Esplora(client))
+            }
+        }
+    }
+
+    // bring the wallet up to the current chain tip from revealed scripts
+    pub(crate) async fn sync(&self, wallet: &SharedWallet) -> Result<(), AppError> {
+        match self {
+            ChainSource::Esplora(client) => {
+                let request = wallet.read().await.start_sync_with_revealed_spks().build();
+                let update = client.sync(request, PARALLEL_REQUESTS).await?;
+                wallet.write().await.apply_update(update)?;
+                Ok(())
+            }
+            ChainSource::Bitcoind(client) => emit_blocks(wallet, client.clone()).await,
+        }
+    }
+
+    // full recovery scan probing `stop_gap` consecutive unused addresses.
+    //
+    // NOTE: this is only equivalent to Esplora's `full_scan` for the Esplora
+    // variant. The Bitcoind variant has no stop-gap notion: it starts the
+    // emitter at the wallet's current tip and walks blocks forward, so
+    // `stop_gap` is ignored and an already-synced wallet will NOT rediscover
+    // funds on newly-revealed gap addresses. Only the fresh-restore case,
+    // where the local tip is genesis, performs a true recovery scan over RPC.
+    pub(crate) async fn full_scan(
+        &self,
+        wallet: &SharedWallet,
+        stop_gap: usize,
+    ) -> Result<(), AppError> {
+        match self {
+            ChainSource::Esplora(client) => {
+                let request = wallet.read().await.start_full_scan().build();
+                let update = client.full_scan(request, stop_gap, PARALLEL_REQUESTS).await?;
+                wallet.write().await.apply_update(update)?;
+                Ok(())
+            }
+            ChainSource::Bitcoind(client) => emit_blocks(wallet, client.clone()).await,
+        }
+    }
+
+    // fee-rate estimates keyed by confirmation-target block count, in sat/vB
+    pub(crate) async fn fee_estimates(&self) -> Result<HashMap<u16, f64>, AppError> {
+        match self {
+            ChainSource::Esplora(client) => Ok(client.get_fee_estimates().await?),
+            ChainSource::Bitcoind(client) => {
+                let client = client.clone();
+                // estimatesmartfee is a blocking RPC; run the lookups off-runtime
+                let estimates = spawn_blocking(move || {
+                    // query each offered target, converting BTC/kvB to sat/vB
+                    let mut estimates = HashMap::new();
+                    for target in [1u16, 3, 6] {
+                        if let Ok(estimate) = client.estimate_smart_fee(target, None) {
+                            if let Some(per_kvb) = estimate.fee_rate {
+                                estimates.insert(target, per_kvb.to_sat() as f64 / 1000.0);
+                            }
+                        }
+                    }
+                    estimates
+                })
+                .await
+                .expect("rpc fee-estimate task panicked");
+                Ok(estimates)
+            }
+        }
+    }
+
+    pub(crate) async fn broadcast(&self, tx: &Transaction) -> Result<(), AppError> {
+        match self {
+            ChainSource::Esplora(client) => {
+                client.broadcast(tx).await?;
+                Ok(())
+            }
+            ChainSource::Bitcoind(client) => {
+                let client = client.clone();
+                let tx = tx.clone();
+                // send_raw_transaction is a blocking RPC; run it off-runtime
+                spawn_blocking(move || client.send_raw_transaction(&tx))
+                    .await
+                    .expect("rpc broadcast task panicked")?;
+                Ok(())
+            }
+        }
+    }
+}
+
+// drive BDK's emitter-based block scanning from the wallet's local tip. The
+// emitter's `next_block`/`mempool` calls are synchronous and can block for
+// minutes during a recovery scan, so the drain runs on a blocking thread;
+// each block is streamed back over a bounded channel and applied to the
+// wallet as it arrives, rather than buffering the whole scanned range (which
+// can be the entire chain on a fresh-restore recovery scan) in memory first.
+async fn emit_blocks(wallet: &SharedWallet, client: Arc<Client>) -> Result<(), AppError> {
+    let tip = wallet.read().await.latest_checkpoint();
+    let start_height = tip.height();
+    debug!("emitting blocks from height {}", start_height);
+
+    let (tx, mut rx) = mpsc::channel(8);
+    let emitter_task = spawn_blocking(move || -> Result<_, AppError> {
+        let mut emitter = Emitter::new(client.as_ref(), tip, start_height);
+        while let Some(event) = emitter.next_block()? {
+            let block = (event.block, event.block_height(), event.connected_to());
+            // the receiving end is dropped if the wallet-apply loop bailed on
+            // an error; stop emitting rather than blocking forever
+            if tx.blocking_send(block).is_err() {
+                break;
+            }
+        }
+        let mempool = emitter.mempool()?;
+        Ok(mempool.new_txs)
+    });
+
+    while let Some((block, height, connected_to)) = rx.recv().await {
+        wallet
+            .write()
+            .await
+            .apply_block_connected_to(&block, height, connected_to)?;
+    }
+
+    let mempool_txs = emitter_task.await.expect("rpc emitter task panicked")?;
+    // pick up unconfirmed transactions from the mempool
+    wallet.write().await.apply_unconfirmed_txs(mempool_txs);
+    Ok(())
+}
@@ -1,13 +1,75 @@
 use crate::error::AppError;
 use crate::{WALLET_NAME, WORD_COUNT};
+use argon2::Argon2;
+use base64::prelude::{Engine, BASE64_STANDARD};
 use bdk_wallet::bip39::{Language, Mnemonic};
 use bdk_wallet::bitcoin::key::rand;
 use bdk_wallet::bitcoin::key::rand::Rng;
 use bdk_wallet::keys::{GeneratableKey, GeneratedKey};
 use bdk_wallet::miniscript::Tap;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
 use sqlx::{Row, Sqlite, Transaction as DbTransaction};
+use std::collections::HashMap;
 use tracing::debug;
 
+// env var holding the passphrase used to derive the mnemonic encryption key
+const PASSPHRASE_VAR: &str = "WALLET_PASSPHRASE";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+// read the configured passphrase, distinguishing "not set" from other failures
+// so a startup misconfiguration isn't reported as a wrong-passphrase error
+fn read_passphrase() -> Result<String, AppError> {
+    std::env::var(PASSPHRASE_VAR).map_err(|_| AppError::PassphraseMissing)
+}
+
+// derive a 32-byte encryption key from the passphrase and salt with Argon2id
+fn derive_key(passphrase: &[u8], salt: &[u8]) -> Result<[u8; KEY_LEN], AppError> {
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(passphrase, salt, &mut key)
+        .map_err(|_| AppError::Crypt)?;
+    Ok(key)
+}
+
+// encrypt a mnemonic under a passphrase, returning base64(salt || nonce || ct)
+fn encrypt_mnemonic(passphrase: &str, mnemonic: &str) -> Result<String, AppError> {
+    let mut rng = rand::thread_rng();
+    let mut salt = [0u8; SALT_LEN];
+    rng.fill(&mut salt);
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut nonce);
+    let key = derive_key(passphrase.as_bytes(), &salt)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), mnemonic.as_bytes())
+        .map_err(|_| AppError::Crypt)?;
+    let mut blob = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    blob.extend_from_slice(&salt);
+    blob.extend_from_slice(&nonce);
+    blob.extend_from_slice(&ciphertext);
+    Ok(BASE64_STANDARD.encode(&blob))
+}
+
+// decrypt a base64(salt || nonce || ct) blob; an auth-tag mismatch (wrong
+// passphrase) surfaces as `AppError::Crypt`
+fn decrypt_mnemonic(passphrase: &str, encoded: &str) -> Result<String, AppError> {
+    let blob = BASE64_STANDARD.decode(encoded).map_err(|_| AppError::Crypt)?;
+    if blob.len() < SALT_LEN + NONCE_LEN {
+        return Err(AppError::Crypt);
+    }
+    let (salt, rest) = blob.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let key = derive_key(passphrase.as_bytes(), salt)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| AppError::Crypt)?;
+    String::from_utf8(plaintext).map_err(|_| AppError::Crypt)
+}
+
 // generate and store a new secret key mnemonic
 pub(crate) async fn store_secret_key_mnemonic(
     tx: &mut DbTransaction<'_, Sqlite>,
@@ -23,17 +85,24 @@ pub(crate) async fn store_secret_key_mnemonic(
         Mnemonic::generate_with_entropy((WORD_COUNT, Language::English), entropy).unwrap();
     let generated_key = generated_key.to_string();
 
+    // encrypt the mnemonic under a passphrase-derived key before storing it
+    let passphrase = read_passphrase()?;
+    let encoded = encrypt_mnemonic(&passphrase, &generated_key)?;
+
     sqlx::query("INSERT INTO keys (wallet_name, mnemonic) VALUES ($1, $2)")
         .bind(WALLET_NAME.to_string())
-        .bind(&generated_key)
+        .bind(&encoded)
         .execute(&mut **tx)
         .await?;
     Ok(generated_key)
 }
 
-// load an existing secret key mnemonic
+// load an existing secret key mnemonic, re-encrypting it in place if it was
+// still stored in the legacy plaintext format (skipped when `read_only`,
+// since that connection can't write the migrated row back)
 pub(crate) async fn load_secret_key_mnemonic(
     tx: &mut DbTransaction<'_, Sqlite>,
+    read_only: bool,
 ) -> Result<Option<String>, AppError> {
     // load mnemonic words if they exist
     let row = sqlx::query::<Sqlite>("SELECT mnemonic FROM keys WHERE wallet_name = $1")
@@ -41,5 +110,96 @@ pub(crate) async fn load_secret_key_mnemonic(
         .fetch_optional(&mut **tx)
         .await?;
     let stored_key: Option<String> = row.map(|r| r.get(0));
-    Ok(stored_key)
+    let encoded = match stored_key {
+        Some(encoded) => encoded,
+        None => return Ok(None),
+    };
+
+    // a baseline row stored the mnemonic as plaintext (space-separated words);
+    // base64 of an encrypted blob never contains a space, so treat such a value
+    // as a legacy un-encrypted mnemonic, return it verbatim rather than failing
+    // to base64-decode and bricking access to existing funds, and re-encrypt it
+    // back into the row immediately so it doesn't linger in plaintext
+    if encoded.contains(' ') {
+        if read_only {
+            debug!("found legacy plaintext mnemonic; leaving as-is (read-only)");
+            return Ok(Some(encoded));
+        }
+        debug!("found legacy plaintext mnemonic; re-encrypting it now");
+        let passphrase = read_passphrase()?;
+        let reencoded = encrypt_mnemonic(&passphrase, &encoded)?;
+        sqlx::query("UPDATE keys SET mnemonic = $1 WHERE wallet_name = $2")
+            .bind(&reencoded)
+            .bind(WALLET_NAME.to_string())
+            .execute(&mut **tx)
+            .await?;
+        return Ok(Some(encoded));
+    }
+
+    // re-derive the key from the passphrase + stored salt and decrypt
+    let passphrase = read_passphrase()?;
+    let mnemonic = decrypt_mnemonic(&passphrase, &encoded)?;
+    Ok(Some(mnemonic))
+}
+
+// upsert a human-readable label (the OP_RETURN note) against a transaction id
+pub(crate) async fn upsert_tx_label(
+    tx: &mut DbTransaction<'_, Sqlite>,
+    txid: &str,
+    label: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO tx_labels (txid, label) VALUES ($1, $2) \
+         ON CONFLICT(txid) DO UPDATE SET label = excluded.label",
+    )
+    .bind(txid)
+    .bind(label)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+// bulk-load all stored transaction labels keyed by txid
+pub(crate) async fn load_tx_labels(
+    tx: &mut DbTransaction<'_, Sqlite>,
+) -> Result<HashMap<String, String>, AppError> {
+    let rows = sqlx::query::<Sqlite>("SELECT txid, label FROM tx_labels")
+        .fetch_all(&mut **tx)
+        .await?;
+    let labels = rows
+        .into_iter()
+        .map(|r| (r.get::<String, _>(0), r.get::<String, _>(1)))
+        .collect();
+    Ok(labels)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn encrypt_decrypt_round_trips() {
+        let encoded = encrypt_mnemonic("correct horse", MNEMONIC).unwrap();
+        assert_eq!(decrypt_mnemonic("correct horse", &encoded).unwrap(), MNEMONIC);
+    }
+
+    #[test]
+    fn encrypt_uses_a_fresh_salt_and_nonce() {
+        // two encryptions of the same mnemonic must not produce the same blob
+        let a = encrypt_mnemonic("pass", MNEMONIC).unwrap();
+        let b = encrypt_mnemonic("pass", MNEMONIC).unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let encoded = encrypt_mnemonic("right", MNEMONIC).unwrap();
+        assert!(matches!(
+            decrypt_mnemonic("wrong", &encoded),
+            Err(AppError::Crypt)
+        ));
+    }
 }
@@ -0,0 +1,61 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::fmt;
+
+// application-wide error type. Named variants cover conditions the app itself
+// detects; `Other` wraps every other fallible call (sqlx, bdk_wallet, ...) so
+// handlers can keep using `?` without mapping each error by hand.
+#[derive(Debug)]
+pub(crate) enum AppError {
+    // a PSBT could not be fully signed/finalized
+    Finalize,
+    // encryption, decryption, or key-derivation failed (e.g. wrong passphrase)
+    Crypt,
+    // WALLET_PASSPHRASE is required but not set
+    PassphraseMissing,
+    // a fiat rate couldn't be parsed out of the price oracle's response, or a
+    // sat/rate conversion overflowed
+    Price,
+    // a read-only reader found no existing mnemonic/wallet to load; it can
+    // never create one, since only a writable process persists new state
+    ReadOnly,
+    // a spend's note (form input or BIP-21 label/message) is too long to fit
+    // in a single OP_RETURN push
+    NoteTooLong,
+    Other(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Finalize => write!(f, "PSBT could not be finalized"),
+            AppError::Crypt => write!(f, "encryption or decryption failed"),
+            AppError::PassphraseMissing => {
+                write!(f, "WALLET_PASSPHRASE is not set")
+            }
+            AppError::Price => write!(f, "fiat rate unavailable"),
+            AppError::ReadOnly => write!(f, "no existing wallet to load in read-only mode"),
+            AppError::NoteTooLong => write!(f, "note is too long"),
+            AppError::Other(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+// lets every other error type (sqlx::Error, bdk_wallet's various error enums,
+// std::io::Error, ...) flow through `?` in a handler that returns
+// `Result<_, AppError>`, without a dedicated variant or map_err at each call site
+impl<E> From<E> for AppError
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    fn from(err: E) -> Self {
+        AppError::Other(Box::new(err))
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        tracing::error!("{self}");
+        (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()).into_response()
+    }
+}
@@ -1,30 +1,40 @@
+mod chain;
 mod db;
 mod error;
+mod price;
 mod template;
 
-use crate::db::{load_secret_key_mnemonic, store_secret_key_mnemonic};
+use crate::db::{
+    load_secret_key_mnemonic, load_tx_labels, store_secret_key_mnemonic, upsert_tx_label,
+};
+use crate::chain::ChainSource;
 use crate::error::AppError;
+use crate::price::PriceOracle;
 use crate::template::home_page;
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::response::{IntoResponse, Redirect};
-use axum::{routing::get, Form, Router};
-use bdk_esplora::esplora_client::AsyncClient;
-use bdk_esplora::{esplora_client, EsploraAsyncExt};
+use axum::http::header;
+use axum::{routing::get, routing::post, Form, Router};
 use bdk_sqlx::Store;
 use bdk_wallet::bip39::{Language, Mnemonic};
 use bdk_wallet::bitcoin::script::PushBytesBuf;
-use bdk_wallet::bitcoin::{Address, Amount, FeeRate, Txid};
+use bdk_wallet::bitcoin::ScriptBuf;
+use bdk_wallet::bitcoin::{Address, Amount, Denomination, FeeRate, OutPoint, Psbt, Txid};
 use bdk_wallet::chain::{ChainPosition, ConfirmationBlockTime};
 use bdk_wallet::descriptor::IntoWalletDescriptor;
 use bdk_wallet::keys::bip39::WordCount::{self, Words12};
+use bdk_wallet::keys::KeyMap;
 use bdk_wallet::template::Bip86;
 use bdk_wallet::KeychainKind::{External, Internal};
 use bdk_wallet::{bitcoin::Network, PersistedWallet, SignOptions, Wallet, WalletTx};
+use rust_decimal::Decimal;
 use serde::Deserialize;
 use sqlx::sqlx_macros::migrate;
 use sqlx::{Sqlite, SqlitePool, Transaction as DbTransaction};
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
 use tokio::sync::RwLock;
 use tracing::debug;
@@ -32,15 +42,51 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilte
 
 const ESPLORA_URL: &str = "https://mutinynet.com/api";
 const PARALLEL_REQUESTS: usize = 5;
+// named confirmation targets offered in the spend form, mapped to the number
+// of blocks the transaction should ideally be confirmed within
+const FEE_TARGETS: [(&str, u16); 3] = [("high priority", 1), ("medium", 3), ("low", 6)];
+// upper bound (sat/vB) applied to fee estimates so malformed oracle values
+// can't overflow `FeeRate::from_sat_per_vb`
+const MAX_FEE_RATE: u64 = 10_000;
 const WORD_COUNT: WordCount = Words12;
 const NETWORK: Network = Network::Signet;
 const DEFAULT_DB_URL: &str = "sqlite://bdk_wallet.sqlite?mode=rwc";
 const WALLET_NAME: &str = "primary";
+// default HTTP bind address, overridable via WALLET_ADDR so a read-only
+// reader process can run alongside the writable one on the same host
+const DEFAULT_ADDR: &str = "127.0.0.1:3000";
+// default number of consecutive unused addresses to probe before a full scan
+// gives up, overridable via the WALLET_STOP_GAP env var or `?stop_gap=` query
+const DEFAULT_STOP_GAP: usize = 20;
+// how often a read-only reader reloads the wallet from the database, so its
+// view eventually reflects writes made by the live writable process
+const READ_ONLY_REFRESH: Duration = Duration::from_secs(5);
+// how long an exported-but-unbroadcast PSBT's inputs stay excluded from coin
+// selection, so an abandoned air-gapped signing round trip doesn't lock
+// those UTXOs out of spending forever
+const PSBT_RESERVATION_TTL: Duration = Duration::from_secs(600);
+// standard relay policy caps an OP_RETURN push at 80 bytes; reject an
+// oversized note up front rather than building a non-standard transaction
+const MAX_NOTE_LEN: usize = 80;
 
 struct AppState {
     wallet: RwLock<PersistedWallet<Store<Sqlite>>>,
     store: RwLock<Store<Sqlite>>,
-    client: AsyncClient,
+    // connection pool for the app's own tables (e.g. transaction labels)
+    pool: SqlitePool,
+    chain: ChainSource,
+    price: PriceOracle,
+    // when true the wallet was opened for reading only: no migrations, no
+    // persisting sync, and the spend route is not mounted
+    read_only: bool,
+    // last time a read-only reader reloaded the wallet from the database;
+    // unused when `read_only` is false
+    last_reload: RwLock<Instant>,
+    // outpoints selected by an exported-but-unbroadcast PSBT, keyed to the
+    // instant they were reserved; excluded from coin selection in `build_tx`
+    // so a concurrent spend/export can't pick the same UTXOs while the
+    // first PSBT is off being signed out-of-process
+    reserved_utxos: RwLock<HashMap<OutPoint, Instant>>,
 }
 
 #[tokio::main]
@@ -54,26 +100,52 @@ async fn main() -> Result<(), AppError> {
         .try_init()
         .expect("init logging");
 
-    // create esplora client
-    let client = esplora_client::Builder::new(ESPLORA_URL).build_async()?;
+    // read-only mode lets a second process inspect the live wallet's database
+    // without tripping write-lock contention
+    let read_only = std::env::var("WALLET_READ_ONLY")
+        .map(|v| v == "1" || v == "true")
+        .unwrap_or(false)
+        || std::env::args().any(|a| a == "--read-only");
 
-    // create database connection pool, URL from env or use default DB URL
-    let database_url = std::env::var("WALLET_DB_URL").unwrap_or(DEFAULT_DB_URL.to_string());
+    // watch-only mode loads the wallet without its private keymap, so
+    // in-process signing (`spend`) can never succeed but the PSBT
+    // export/broadcast routes still work against the same descriptors,
+    // signed by an external (e.g. air-gapped) wallet instead
+    let watch_only = std::env::var("WALLET_WATCH_ONLY")
+        .map(|v| v == "1" || v == "true")
+        .unwrap_or(false);
+
+    // create the configured chain data source (Esplora by default, or a
+    // Bitcoin Core RPC node when WALLET_CHAIN_BACKEND is set)
+    let chain = ChainSource::from_env().await?;
+
+    // create database connection pool, URL from env or use a default that
+    // opens the store read-only when no migrations or writes are wanted
+    let database_url = std::env::var("WALLET_DB_URL").unwrap_or_else(|_| {
+        if read_only {
+            "sqlite://bdk_wallet.sqlite?mode=ro".to_string()
+        } else {
+            DEFAULT_DB_URL.to_string()
+        }
+    });
     debug!("database_url: {:?}", &database_url);
 
-    // run database schema migrations
+    // run database schema migrations (skipped in read-only mode)
     let pool = SqlitePool::connect(database_url.as_str()).await?;
-    migrate!("./migrations").run(&pool).await?;
+    if !read_only {
+        migrate!("./migrations").run(&pool).await?;
+    }
 
     // create wallet database store
     let mut store: Store<Sqlite> =
         Store::<Sqlite>::new(pool.clone(), Some(WALLET_NAME.to_string()), false).await?;
 
-    // load or create and store new BIP-39 secret key mnemonic
+    // load the BIP-39 secret key mnemonic, creating one only when writable
     let mut tx: DbTransaction<Sqlite> = pool.begin().await?;
-    let loaded_key = load_secret_key_mnemonic(&mut tx).await?;
+    let loaded_key = load_secret_key_mnemonic(&mut tx, read_only).await?;
     let mnemonic = match loaded_key {
         Some(mnemonic) => mnemonic,
+        None if read_only => return Err(AppError::ReadOnly),
         None => store_secret_key_mnemonic(&mut tx).await?,
     };
     let mnemonic = Mnemonic::parse_in(Language::English, mnemonic)?;
@@ -88,8 +160,18 @@ async fn main() -> Result<(), AppError> {
         Bip86(mnemonic.clone(), Internal).into_wallet_descriptor(&Default::default(), NETWORK)?;
     debug!("internal_descriptor: {}", &internal_descriptor);
 
-    // load or create and store a new wallet
-    let loaded_wallet = Wallet::load()
+    // in watch-only mode, drop the derived keymaps so the in-memory wallet
+    // never holds private key material, even though the descriptors (and
+    // therefore addresses) are unchanged
+    let (external_keymap, internal_keymap) = if watch_only {
+        (KeyMap::new(), KeyMap::new())
+    } else {
+        (external_keymap, internal_keymap)
+    };
+
+    // load or create and store a new wallet; extract_keys() only applies
+    // when the wallet is allowed to hold private keys
+    let mut load_builder = Wallet::load()
         .descriptor(
             External,
             Some((external_descriptor.clone(), external_keymap.clone())),
@@ -98,12 +180,14 @@ async fn main() -> Result<(), AppError> {
             Internal,
             Some((internal_descriptor.clone(), internal_keymap.clone())),
         )
-        .extract_keys()
-        .check_network(NETWORK)
-        .load_wallet_async(&mut store)
-        .await?;
+        .check_network(NETWORK);
+    if !watch_only {
+        load_builder = load_builder.extract_keys();
+    }
+    let loaded_wallet = load_builder.load_wallet_async(&mut store).await?;
     let wallet = match loaded_wallet {
         Some(wallet) => wallet,
+        None if read_only => return Err(AppError::ReadOnly),
         None => {
             Wallet::create(
                 (external_descriptor, external_keymap),
@@ -119,44 +203,104 @@ async fn main() -> Result<(), AppError> {
     let state = Arc::new(AppState {
         wallet: RwLock::new(wallet),
         store: RwLock::new(store),
-        client,
+        pool,
+        chain,
+        price: PriceOracle::new(),
+        read_only,
+        last_reload: RwLock::new(Instant::now()),
+        reserved_utxos: RwLock::new(HashMap::new()),
     });
 
-    // configure web server routes
-    let app = Router::new()
-        .route("/", get(home).post(spend))
-        .with_state(state);
+    // configure web server routes; read-only mode mounts only read routes and
+    // disables spending and the persisting rescan path; watch-only mode keeps
+    // rescan and the PSBT export/broadcast routes (no private keys needed)
+    // but drops in-process spending, which can never finalize without a keymap
+    let app = if read_only {
+        Router::new().route("/", get(home)).with_state(state)
+    } else if watch_only {
+        Router::new()
+            .route("/", get(home))
+            .route("/rescan", get(rescan))
+            .route("/psbt", post(psbt_export))
+            .route("/psbt/broadcast", post(psbt_broadcast))
+            .with_state(state)
+    } else {
+        Router::new()
+            .route("/", get(home).post(spend))
+            .route("/rescan", get(rescan))
+            .route("/psbt", post(psbt_export))
+            .route("/psbt/broadcast", post(psbt_broadcast))
+            .with_state(state)
+    };
 
-    // start the web server
-    let listener = TcpListener::bind("127.0.0.1:3000").await?;
+    // start the web server; WALLET_ADDR lets a read-only reader bind to a
+    // different address than the writable instance it's inspecting
+    let addr = std::env::var("WALLET_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string());
+    let listener = TcpListener::bind(&addr).await?;
     debug!("listening on: http://{}", listener.local_addr()?);
     axum::serve(listener, app).await.map_err(|e| e.into())
 }
 
+// reload the wallet from the database, re-deriving its descriptors from the
+// stored mnemonic; mirrors the startup load in `main` and is used by a
+// read-only reader to periodically pick up writes from the live process
+async fn reload_wallet(
+    pool: &SqlitePool,
+    store: &mut Store<Sqlite>,
+) -> Result<PersistedWallet<Store<Sqlite>>, AppError> {
+    let mut tx: DbTransaction<Sqlite> = pool.begin().await?;
+    // only a read-only reader calls reload_wallet, so never re-encrypt here
+    let mnemonic = load_secret_key_mnemonic(&mut tx, true)
+        .await?
+        .ok_or(AppError::ReadOnly)?;
+    let mnemonic = Mnemonic::parse_in(Language::English, mnemonic)?;
+    tx.commit().await?;
+
+    let (external_descriptor, external_keymap) =
+        Bip86(mnemonic.clone(), External).into_wallet_descriptor(&Default::default(), NETWORK)?;
+    let (internal_descriptor, internal_keymap) =
+        Bip86(mnemonic, Internal).into_wallet_descriptor(&Default::default(), NETWORK)?;
+
+    Wallet::load()
+        .descriptor(External, Some((external_descriptor, external_keymap)))
+        .descriptor(Internal, Some((internal_descriptor, internal_keymap)))
+        .extract_keys()
+        .check_network(NETWORK)
+        .load_wallet_async(store)
+        .await?
+        .ok_or(AppError::ReadOnly)
+}
+
 // web page handlers
 
 async fn home(State(state): State<Arc<AppState>>) -> Result<impl IntoResponse, AppError> {
-    debug!("syncing");
-    let sync_result = {
-        // use wallet read-only lock during esplora client sync, drop lock after sync
-        let sync_request = state
-            .wallet
-            .read()
-            .await
-            .start_sync_with_revealed_spks()
-            .build();
-        state.client.sync(sync_request, PARALLEL_REQUESTS).await?
-    };
+    if state.read_only {
+        // a reader never syncs or writes, but its in-memory wallet was loaded
+        // once at startup; periodically reload it from the database so the
+        // view eventually reflects what the writable process has persisted
+        if state.last_reload.read().await.elapsed() >= READ_ONLY_REFRESH {
+            debug!("reloading wallet from database");
+            let mut store = state.store.write().await;
+            let wallet = reload_wallet(&state.pool, &mut store).await?;
+            drop(store);
+            *state.wallet.write().await = wallet;
+            *state.last_reload.write().await = Instant::now();
+        }
+    } else {
+        // bring the wallet up to tip and persist
+        debug!("syncing");
+        state.chain.sync(&state.wallet).await?;
+    }
 
-    // after sync get wallet write lock to update and persist changes
+    // after sync get wallet write lock to reveal the next address and persist
     let next_unused_address = {
         let mut wallet = state.wallet.write().await;
-        debug!("apply update");
-        wallet.apply_update(sync_result)?;
         let next_unused_address = wallet.next_unused_address(External).address;
-        debug!("storing");
-        let mut store = state.store.write().await;
-        wallet.persist_async(&mut store).await?;
+        if !state.read_only {
+            debug!("storing");
+            let mut store = state.store.write().await;
+            wallet.persist_async(&mut store).await?;
+        }
         next_unused_address
     };
 
@@ -169,18 +313,120 @@ async fn home(State(state): State<Arc<AppState>>) -> Result<impl IntoResponse, A
         .map(|tx| TxDetails::new(tx, &wallet))
         .collect::<Vec<_>>();
     txs.sort_by(|tx1, tx2| tx1.chain_position.cmp(&tx2.chain_position));
+    drop(wallet);
+
+    // fiat valuation: historical rate at the confirmation block time for
+    // confirmed transactions, spot otherwise; total balance uses spot. A
+    // fetch failure or an overflowing conversion must not take down the whole
+    // page, so either degrades that one figure to `None` rather than erroring
+    // out of the handler.
+    let balance_fiat = match state.price.spot().await {
+        Ok(rate) => state.price.to_fiat(balance.total().to_sat(), rate).ok(),
+        Err(_) => None,
+    };
+    for tx in txs.iter_mut() {
+        if let Ok(rate) = state.price.rate_at(tx.confirmation_time()).await {
+            tx.fiat = state.price.to_fiat(tx.net_sats(), rate).ok();
+        }
+    }
+
+    // join locally stored labels so each row shows its human-readable memo
+    let mut db_tx: DbTransaction<Sqlite> = state.pool.begin().await?;
+    let labels = load_tx_labels(&mut db_tx).await?;
+    db_tx.commit().await?;
+    for tx in txs.iter_mut() {
+        tx.label = labels.get(&tx.txid.to_string()).cloned();
+    }
+
+    // fetch current network fee estimates so the spend form can offer named
+    // confirmation targets instead of asking for a raw sats/vbyte rate; a
+    // fee-oracle outage must not take down the balance view, so fall back to
+    // an empty map (resolve_fee_rate then yields the minimum rate)
+    debug!("fetching fee estimates");
+    let estimates = state.chain.fee_estimates().await.unwrap_or_default();
+    let fee_targets = FEE_TARGETS
+        .iter()
+        .map(|(name, target)| (*name, *target, resolve_fee_rate(&estimates, *target)))
+        .collect::<Vec<_>>();
 
     // render home page from template
-    Ok(home_page(next_unused_address, balance, txs))
+    Ok(home_page(
+        next_unused_address,
+        balance,
+        balance_fiat,
+        txs,
+        fee_targets,
+    ))
 }
 
-struct TxDetails {
-    txid: Txid,
-    sent: Amount,
-    received: Amount,
-    fee: Amount,
-    fee_rate: FeeRate,
+// resolve a confirmation target (in blocks) to a concrete `FeeRate` from the
+// chain source's fee-estimate map, picking the nearest available target at or
+// below the requested one and falling back to 1 sat/vB when none is offered
+fn resolve_fee_rate(estimates: &HashMap<u16, f64>, target: u16) -> FeeRate {
+    let sat_per_vb = estimates
+        .iter()
+        // discard non-finite/negative rates from malformed external input before
+        // picking the nearest target, so a bad entry doesn't shadow a valid
+        // lower-block estimate
+        .filter(|(blocks, rate)| **blocks <= target && rate.is_finite() && **rate >= 0.0)
+        .max_by_key(|(blocks, _)| **blocks)
+        // clamp into a sane range before converting so we never overflow
+        .map(|(_, rate)| rate.ceil().min(MAX_FEE_RATE as f64) as u64)
+        .unwrap_or(1)
+        .clamp(1, MAX_FEE_RATE);
+    FeeRate::from_sat_per_vb(sat_per_vb).expect("fee rate within valid range")
+}
+
+#[derive(Deserialize, Debug)]
+struct RescanRequest {
+    stop_gap: Option<usize>,
+}
+
+// recover funds on addresses beyond the currently revealed range by running a
+// full scan with a configurable stop-gap, then persisting the update; useful
+// for importing a pre-existing mnemonic rather than only tracking new receives
+async fn rescan(
+    State(state): State<Arc<AppState>>,
+    Query(rescan): Query<RescanRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    // stop-gap from the query parameter, else the WALLET_STOP_GAP env var, else default
+    let stop_gap = rescan
+        .stop_gap
+        .or_else(|| {
+            std::env::var("WALLET_STOP_GAP")
+                .ok()
+                .and_then(|s| s.parse().ok())
+        })
+        .unwrap_or(DEFAULT_STOP_GAP);
+    debug!("full scan with stop_gap {}", stop_gap);
+
+    // run the recovery scan through the configured chain source
+    state.chain.full_scan(&state.wallet, stop_gap).await?;
+
+    // after scan get wallet write lock to persist changes
+    {
+        let mut wallet = state.wallet.write().await;
+        debug!("storing");
+        let mut store = state.store.write().await;
+        wallet.persist_async(&mut store).await?;
+    }
+
+    Ok(Redirect::to("/"))
+}
+
+// rendered by `template::home_page`; fields are pub(crate) so the template
+// module can read them without reaching into wallet/chain types directly
+pub(crate) struct TxDetails {
+    pub(crate) txid: Txid,
+    pub(crate) sent: Amount,
+    pub(crate) received: Amount,
+    pub(crate) fee: Amount,
+    pub(crate) fee_rate: FeeRate,
     chain_position: ChainPosition<ConfirmationBlockTime>,
+    // fiat value of the net sent/received amount, when a rate is available
+    pub(crate) fiat: Option<Decimal>,
+    // locally stored human-readable memo joined by txid
+    pub(crate) label: Option<String>,
 }
 
 impl<'a> TxDetails {
@@ -206,52 +452,229 @@ impl<'a> TxDetails {
             fee,
             fee_rate,
             chain_position,
+            fiat: None,
+            label: None,
+        }
+    }
+
+    // net sat amount this row represents (received for incoming, sent for outgoing)
+    fn net_sats(&self) -> u64 {
+        if self.received > Amount::ZERO {
+            self.received.to_sat()
+        } else {
+            self.sent.to_sat()
+        }
+    }
+
+    // confirmation block time for a historical rate lookup, if confirmed
+    fn confirmation_time(&self) -> Option<u64> {
+        match &self.chain_position {
+            ChainPosition::Confirmed { anchor, .. } => Some(anchor.confirmation_time),
+            ChainPosition::Unconfirmed { .. } => None,
         }
     }
+
+    // whether this transaction has a confirming anchor yet, for the template
+    // to render a status column without needing chain-position types itself
+    pub(crate) fn confirmed(&self) -> bool {
+        matches!(self.chain_position, ChainPosition::Confirmed { .. })
+    }
 }
 
 #[derive(Deserialize, Debug)]
 struct SpendRequest {
     address: String,
     amount: String,
-    fee_rate: String,
+    // number of blocks to target for confirmation, resolved to a concrete fee
+    // rate against the chain source's fee estimates (see `FEE_TARGETS`)
+    fee_target: String,
     note: String,
 }
 
+// a recipient parsed from the spend form, either a bare address or a BIP-21
+// payment URI whose amount and label/message may override the form fields
+struct Payment {
+    address: String,
+    amount: Option<Amount>,
+    note: Option<String>,
+}
+
+// parse a `bitcoin:<address>?amount=<btc>&label=<..>&message=<..>` URI,
+// converting the BTC-denominated amount to an `Amount` and folding
+// label/message into a note for the OP_RETURN
+fn parse_bip21(input: &str) -> Result<Payment, AppError> {
+    let rest = input.trim_start_matches("bitcoin:");
+    let (address, query) = match rest.split_once('?') {
+        Some((address, query)) => (address.to_string(), Some(query)),
+        None => (rest.to_string(), None),
+    };
+    let mut amount = None;
+    let mut label = None;
+    let mut message = None;
+    for (key, value) in query.into_iter().flat_map(|q| q.split('&')).filter_map(|p| {
+        p.split_once('=')
+            .map(|(k, v)| (k.to_string(), percent_decode(v)))
+    }) {
+        match key.as_str() {
+            "amount" => amount = Some(Amount::from_str_in(&value, Denomination::Bitcoin)?),
+            "label" => label = Some(value),
+            "message" => message = Some(value),
+            _ => {}
+        }
+    }
+    let note = match (label, message) {
+        (Some(label), Some(message)) => Some(format!("{label} {message}")),
+        (Some(text), None) | (None, Some(text)) => Some(text),
+        (None, None) => None,
+    };
+    Ok(Payment {
+        address,
+        amount,
+        note,
+    })
+}
+
+// minimal percent-decoding for BIP-21 query values; decodes into a byte buffer
+// and interprets the whole thing as UTF-8 so multi-byte sequences survive.
+// BIP-21 query strings are RFC 3986, not application/x-www-form-urlencoded,
+// so '+' is a literal plus and is left untouched here.
+fn percent_decode(value: &str) -> String {
+    let mut out = Vec::with_capacity(value.len());
+    let mut bytes = value.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'%' => {
+                let hi = bytes.next().and_then(|c| (c as char).to_digit(16));
+                let lo = bytes.next().and_then(|c| (c as char).to_digit(16));
+                match (hi, lo) {
+                    (Some(hi), Some(lo)) => out.push((hi * 16 + lo) as u8),
+                    _ => out.push(b'%'),
+                }
+            }
+            _ => out.push(b),
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// a validated spend request with its network calls (fee estimation) already
+// resolved, ready to drive `build_tx` under a single wallet write lock
+struct PreparedSpend {
+    script_pubkey: ScriptBuf,
+    amount: Amount,
+    fee_rate: FeeRate,
+    note: PushBytesBuf,
+    note_text: String,
+}
+
+// validate a spend request and resolve its fee rate without touching the
+// wallet, so the async fee-estimate fetch never happens while holding the lock
+async fn prepare_spend(state: &AppState, spend: &SpendRequest) -> Result<PreparedSpend, AppError> {
+    // the address field may also carry a full BIP-21 payment URI, whose amount
+    // overrides the form's amount and whose label/message fill an empty note
+    let payment = if spend.address.starts_with("bitcoin:") {
+        parse_bip21(&spend.address)?
+    } else {
+        Payment {
+            address: spend.address.clone(),
+            amount: None,
+            note: None,
+        }
+    };
+    let amount = match payment.amount {
+        Some(amount) => amount,
+        None => Amount::from_sat(u64::from_str(spend.amount.as_str())?),
+    };
+    let address = Address::from_str(&payment.address)?.require_network(NETWORK)?;
+    let script_pubkey = address.script_pubkey();
+    // resolve the requested confirmation target to a fee rate from the chain source
+    let fee_target = u16::from_str(spend.fee_target.as_str())?;
+    let estimates = state.chain.fee_estimates().await?;
+    let fee_rate = resolve_fee_rate(&estimates, fee_target);
+    // prefer the explicit form note, falling back to the URI label/message
+    let note_text = if spend.note.is_empty() {
+        payment.note.clone().unwrap_or_default()
+    } else {
+        spend.note.clone()
+    };
+    // note_text may come straight from a pasted BIP-21 URI's label/message, so
+    // it's untrusted; reject a note longer than MAX_NOTE_LEN, which would
+    // make a non-standard OP_RETURN output
+    if note_text.len() > MAX_NOTE_LEN {
+        return Err(AppError::NoteTooLong);
+    }
+    let note = PushBytesBuf::try_from(note_text.clone().into_bytes())
+        .map_err(|_| AppError::NoteTooLong)?;
+
+    Ok(PreparedSpend {
+        script_pubkey,
+        amount,
+        fee_rate,
+        note,
+        note_text,
+    })
+}
+
+// outpoints currently excluded from coin selection because an unsigned PSBT
+// spending them was already exported and is awaiting an external signature;
+// entries older than PSBT_RESERVATION_TTL are dropped so an abandoned
+// air-gapped signing round trip doesn't lock those UTXOs out forever
+async fn reserved_outpoints(state: &AppState) -> Vec<OutPoint> {
+    let mut reserved = state.reserved_utxos.write().await;
+    reserved.retain(|_, reserved_at| reserved_at.elapsed() < PSBT_RESERVATION_TTL);
+    reserved.keys().copied().collect()
+}
+
+// build an unsigned PSBT from a prepared spend against a held wallet lock, so
+// callers keep one critical section across build (and sign, for the in-process
+// path) and two concurrent spends can't select overlapping UTXOs; `reserved`
+// additionally excludes inputs already claimed by an exported-but-unbroadcast
+// PSBT awaiting an external signature
+fn build_tx(
+    wallet: &mut PersistedWallet<Store<Sqlite>>,
+    prepared: &PreparedSpend,
+    reserved: &[OutPoint],
+) -> Result<Psbt, AppError> {
+    let mut tx_builder = wallet.build_tx();
+    tx_builder.add_recipient(prepared.script_pubkey.clone(), prepared.amount);
+    tx_builder.fee_rate(prepared.fee_rate);
+    tx_builder.add_data(&prepared.note);
+    tx_builder.unspendable(reserved.to_vec());
+    Ok(tx_builder.finish()?)
+}
+
 async fn spend(
     State(state): State<Arc<AppState>>,
     Form(spend): Form<SpendRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     // validate form inputs
     debug!(
-        "spend {} sats to address {} with fee rate {} sats/vbyte",
-        &spend.amount, &spend.address, &spend.fee_rate
+        "spend {} sats to address {} targeting confirmation in {} blocks",
+        &spend.amount, &spend.address, &spend.fee_target
     );
-    let amount = Amount::from_sat(u64::from_str(spend.amount.as_str())?);
-    let address = Address::from_str(&spend.address)?.require_network(NETWORK)?;
-    let script_pubkey = address.script_pubkey();
-    let fee_rate =
-        FeeRate::from_sat_per_vb(u64::from_str(spend.fee_rate.as_str())?).expect("valid fee rate");
-    let note = spend.note.into_bytes();
-    let note = PushBytesBuf::try_from(note).unwrap();
+    let prepared = prepare_spend(&state, &spend).await?;
+    let note_text = prepared.note_text.clone();
 
+    // hold a single write lock across build and sign so concurrent spends can't
+    // select overlapping UTXOs before either signs; read the reserved set
+    // inside this same critical section, or a racing psbt_export could
+    // reserve inputs after we snapshot but before we lock, and both calls
+    // would select the same UTXOs
     let mut wallet = state.wallet.write().await;
-
-    // create and sign PSBT
-    let (psbt, is_finalized) = {
-        let mut tx_builder = wallet.build_tx();
-        tx_builder.add_recipient(script_pubkey, amount);
-        tx_builder.fee_rate(fee_rate);
-        tx_builder.add_data(&note);
-        let mut psbt = tx_builder.finish()?;
-        let is_finalized = wallet.sign(&mut psbt, SignOptions::default())?;
-        (psbt, is_finalized)
-    };
+    let reserved = reserved_outpoints(&state).await;
+    let mut psbt = build_tx(&mut wallet, &prepared, &reserved)?;
+    let is_finalized = wallet.sign(&mut psbt, SignOptions::default())?;
 
     // broadcast finalized transaction
     if is_finalized {
         let tx = &psbt.extract_tx()?;
-        state.client.broadcast(tx).await?;
+        state.chain.broadcast(tx).await?;
+        // record the note locally against the txid so it survives in the UI
+        if !note_text.is_empty() {
+            let mut db_tx: DbTransaction<Sqlite> = state.pool.begin().await?;
+            upsert_tx_label(&mut db_tx, &tx.compute_txid().to_string(), &note_text).await?;
+            db_tx.commit().await?;
+        }
         // need to store wallet with new internal (change) index
         let mut store = state.store.write().await;
         wallet.persist_async(&mut store).await?;
@@ -261,3 +684,146 @@ async fn spend(
         Err(AppError::Finalize)
     }
 }
+
+// build the transaction and return the *unsigned* PSBT (base64) as a
+// downloadable file, for moving to an air-gapped signer
+async fn psbt_export(
+    State(state): State<Arc<AppState>>,
+    Form(spend): Form<SpendRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    debug!("exporting unsigned psbt");
+    let prepared = prepare_spend(&state, &spend).await?;
+    let psbt = {
+        // read the reserved set inside the wallet write lock, matching
+        // `spend`, so a racing build+reserve can't be missed by this one
+        let mut wallet = state.wallet.write().await;
+        let reserved = reserved_outpoints(&state).await;
+        let psbt = build_tx(&mut wallet, &prepared, &reserved)?;
+        // building reveals a change address in memory; persist the revealed
+        // index so a restart before the signed PSBT is broadcast can't reuse
+        // that change address on the next build
+        let mut store = state.store.write().await;
+        wallet.persist_async(&mut store).await?;
+
+        // reserve the inputs this export just selected so a concurrent spend
+        // or export can't pick the same UTXOs while this PSBT is off being
+        // signed out-of-process; this must happen before the wallet lock is
+        // released, or a racing call could snapshot reserved_outpoints()
+        // before this export's inputs are visible in it
+        let now = Instant::now();
+        let mut reserved_utxos = state.reserved_utxos.write().await;
+        for input in &psbt.unsigned_tx.input {
+            reserved_utxos.insert(input.previous_output, now);
+        }
+        psbt
+    };
+
+    let headers = [(
+        header::CONTENT_DISPOSITION,
+        "attachment; filename=\"unsigned.psbt\"",
+    )];
+    Ok((headers, psbt.to_string()))
+}
+
+#[derive(Deserialize, Debug)]
+struct PsbtRequest {
+    psbt: String,
+}
+
+// accept a signed PSBT, finalize it, and broadcast the resulting transaction;
+// works for watch-only wallets loaded without private keys
+async fn psbt_broadcast(
+    State(state): State<Arc<AppState>>,
+    Form(request): Form<PsbtRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    debug!("broadcasting signed psbt");
+    let mut psbt = Psbt::from_str(request.psbt.trim())?;
+
+    let mut wallet = state.wallet.write().await;
+    let is_finalized = wallet.finalize_psbt(&mut psbt, SignOptions::default())?;
+    if is_finalized {
+        let tx = &psbt.extract_tx()?;
+        state.chain.broadcast(tx).await?;
+        // these inputs are now spent on-chain; release their reservation so a
+        // leftover entry can't shadow future coin selection
+        {
+            let mut reserved_utxos = state.reserved_utxos.write().await;
+            for input in &tx.input {
+                reserved_utxos.remove(&input.previous_output);
+            }
+        }
+        // persist any revealed change index recorded when the PSBT was built
+        let mut store = state.store.write().await;
+        wallet.persist_async(&mut store).await?;
+        Ok(Redirect::to("/"))
+    } else {
+        debug!("non-finalized psbt: {}", &psbt);
+        Err(AppError::Finalize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolve_fee_rate_picks_nearest_at_or_below() {
+        let estimates = HashMap::from([(1, 20.0), (3, 10.0), (6, 5.0)]);
+        // exact match
+        assert_eq!(resolve_fee_rate(&estimates, 3).to_sat_per_vb_ceil(), 10);
+        // nearest target at or below 4 is 3
+        assert_eq!(resolve_fee_rate(&estimates, 4).to_sat_per_vb_ceil(), 10);
+        // only the 1-block target qualifies at or below 1
+        assert_eq!(resolve_fee_rate(&estimates, 1).to_sat_per_vb_ceil(), 20);
+    }
+
+    #[test]
+    fn resolve_fee_rate_falls_back_when_empty() {
+        let estimates = HashMap::new();
+        assert_eq!(resolve_fee_rate(&estimates, 6).to_sat_per_vb_ceil(), 1);
+    }
+
+    #[test]
+    fn resolve_fee_rate_rejects_non_finite_and_clamps() {
+        // garbage infinite/NaN rates must not panic; they are discarded so the
+        // minimum applies
+        let estimates = HashMap::from([(1, f64::INFINITY), (3, f64::NAN)]);
+        assert_eq!(resolve_fee_rate(&estimates, 3).to_sat_per_vb_ceil(), 1);
+        // a malformed nearest entry must not shadow a valid lower-block estimate
+        let estimates = HashMap::from([(1, 20.0), (3, f64::NAN)]);
+        assert_eq!(resolve_fee_rate(&estimates, 3).to_sat_per_vb_ceil(), 20);
+        // an absurdly large rate is clamped to the upper bound
+        let estimates = HashMap::from([(1, 1e30)]);
+        assert_eq!(
+            resolve_fee_rate(&estimates, 1).to_sat_per_vb_ceil(),
+            MAX_FEE_RATE
+        );
+    }
+
+    #[test]
+    fn percent_decode_preserves_multibyte_utf8() {
+        assert_eq!(percent_decode("caf%C3%A9"), "café");
+        // '+' is RFC 3986 literal in a BIP-21 query, not a form-encoded space
+        assert_eq!(percent_decode("a+b%20c"), "a+b c");
+        // a stray percent is passed through untouched
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+
+    #[test]
+    fn parse_bip21_extracts_amount_and_note() {
+        let payment =
+            parse_bip21("bitcoin:tb1qexample?amount=0.001&label=Coffee&message=Thanks%20%E2%98%95")
+                .unwrap();
+        assert_eq!(payment.address, "tb1qexample");
+        assert_eq!(payment.amount, Some(Amount::from_sat(100_000)));
+        assert_eq!(payment.note.as_deref(), Some("Coffee Thanks ☕"));
+    }
+
+    #[test]
+    fn parse_bip21_bare_address_has_no_overrides() {
+        let payment = parse_bip21("bitcoin:tb1qexample").unwrap();
+        assert_eq!(payment.address, "tb1qexample");
+        assert_eq!(payment.amount, None);
+        assert_eq!(payment.note, None);
+    }
+}
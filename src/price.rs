@@ -0,0 +1,166 @@
+use crate::error::AppError;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::debug;
+
+// default spot BTC/fiat endpoint; returns a JSON object keyed by fiat symbol,
+// e.g. `{"time":1700000000,"USD":65000,...}`. Overridable via WALLET_PRICE_URL.
+const DEFAULT_PRICE_URL: &str = "https://mempool.space/api/v1/prices";
+// default historical endpoint, queried as `?currency=<fiat>&timestamp=<secs>`
+// and returning `{"prices":[{"time":…,"<FIAT>":…}], …}`. Overridable via
+// WALLET_PRICE_HISTORICAL_URL.
+const DEFAULT_HISTORICAL_URL: &str = "https://mempool.space/api/v1/historical-price";
+const DEFAULT_CURRENCY: &str = "USD";
+const CACHE_TTL: Duration = Duration::from_secs(60);
+const SATS_PER_BTC: i64 = 100_000_000;
+
+// fetches and caches BTC/fiat rates and converts sat amounts to fiat
+pub(crate) struct PriceOracle {
+    url: String,
+    historical_url: String,
+    // fiat symbol to read out of the JSON response (e.g. "USD")
+    currency: String,
+    // cached spot rate with the instant it was fetched
+    spot: RwLock<Option<(Instant, Decimal)>>,
+    // historical rates keyed by block time; these never change so they're
+    // cached indefinitely rather than fetched once per transaction per render
+    historical: RwLock<HashMap<u64, Decimal>>,
+}
+
+impl PriceOracle {
+    pub(crate) fn new() -> Self {
+        let url =
+            std::env::var("WALLET_PRICE_URL").unwrap_or_else(|_| DEFAULT_PRICE_URL.to_string());
+        let historical_url = std::env::var("WALLET_PRICE_HISTORICAL_URL")
+            .unwrap_or_else(|_| DEFAULT_HISTORICAL_URL.to_string());
+        let currency =
+            std::env::var("WALLET_PRICE_CURRENCY").unwrap_or_else(|_| DEFAULT_CURRENCY.to_string());
+        PriceOracle {
+            url,
+            historical_url,
+            currency,
+            spot: RwLock::new(None),
+            historical: RwLock::new(HashMap::new()),
+        }
+    }
+
+    // current spot rate, served from cache while within the TTL
+    pub(crate) async fn spot(&self) -> Result<Decimal, AppError> {
+        if let Some((fetched, rate)) = *self.spot.read().await {
+            if fetched.elapsed() < CACHE_TTL {
+                return Ok(rate);
+            }
+        }
+        let rate = self.fetch_spot().await?;
+        *self.spot.write().await = Some((Instant::now(), rate));
+        Ok(rate)
+    }
+
+    // historical rate at a block time, falling back to spot when unavailable;
+    // successful lookups are memoized by timestamp so a render doesn't
+    // re-fetch that row (a failed lookup is retried on the next call)
+    pub(crate) async fn rate_at(&self, timestamp: Option<u64>) -> Result<Decimal, AppError> {
+        let timestamp = match timestamp {
+            Some(timestamp) => timestamp,
+            None => return self.spot().await,
+        };
+        if let Some(rate) = self.historical.read().await.get(&timestamp).copied() {
+            return Ok(rate);
+        }
+        match self.fetch_historical(timestamp).await {
+            Ok(rate) => {
+                self.historical.write().await.insert(timestamp, rate);
+                Ok(rate)
+            }
+            Err(_) => self.spot().await,
+        }
+    }
+
+    async fn fetch_spot(&self) -> Result<Decimal, AppError> {
+        debug!("fetching spot price from {}", &self.url);
+        let body = reqwest::get(&self.url).await?.text().await?;
+        let json: Value = serde_json::from_str(&body).map_err(|_| AppError::Price)?;
+        json_decimal(&json, &self.currency).ok_or(AppError::Price)
+    }
+
+    async fn fetch_historical(&self, timestamp: u64) -> Result<Decimal, AppError> {
+        let url = format!(
+            "{}?currency={}&timestamp={}",
+            self.historical_url, self.currency, timestamp
+        );
+        debug!("fetching historical price from {}", &url);
+        let body = reqwest::get(&url).await?.text().await?;
+        let json: Value = serde_json::from_str(&body).map_err(|_| AppError::Price)?;
+        json["prices"]
+            .get(0)
+            .and_then(|price| json_decimal(price, &self.currency))
+            .ok_or(AppError::Price)
+    }
+
+    // convert a sat amount to fiat with decimal arithmetic, rounded to cents
+    pub(crate) fn to_fiat(&self, sats: u64, rate: Decimal) -> Result<Decimal, AppError> {
+        let btc = Decimal::from(sats)
+            .checked_div(Decimal::from(SATS_PER_BTC))
+            .ok_or(AppError::Price)?;
+        let fiat = btc.checked_mul(rate).ok_or(AppError::Price)?;
+        Ok(fiat.round_dp(2))
+    }
+}
+
+// read a fiat field out of a JSON object as a `Decimal`, accepting either a
+// JSON number or a string so precision survives the round-trip
+fn json_decimal(value: &Value, key: &str) -> Option<Decimal> {
+    match value.get(key)? {
+        Value::Number(number) => number.to_string().parse().ok(),
+        Value::String(string) => string.parse().ok(),
+        _ => None,
+    }
+}
+
+// helper for templates that want the fiat value as an f64 for formatting
+pub(crate) fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn to_fiat_rounds_to_cents() {
+        let oracle = PriceOracle::new();
+        let rate = Decimal::from(65_000);
+        // 1 BTC at 65,000 is exactly 65,000.00
+        assert_eq!(
+            oracle.to_fiat(100_000_000, rate).unwrap(),
+            Decimal::from(65_000)
+        );
+        // 50,000 sats is 0.0005 BTC -> 32.50
+        assert_eq!(
+            oracle.to_fiat(50_000, rate).unwrap(),
+            Decimal::from_str("32.50").unwrap()
+        );
+        // sub-cent values round to the nearest cent
+        assert_eq!(
+            oracle.to_fiat(1, rate).unwrap(),
+            Decimal::from_str("0.00").unwrap()
+        );
+        assert_eq!(oracle.to_fiat(0, rate).unwrap(), Decimal::ZERO);
+    }
+
+    #[test]
+    fn json_decimal_reads_number_and_string() {
+        let json: Value = serde_json::from_str(r#"{"USD": 65000, "EUR": "60000.5"}"#).unwrap();
+        assert_eq!(json_decimal(&json, "USD"), Some(Decimal::from(65_000)));
+        assert_eq!(
+            json_decimal(&json, "EUR"),
+            Some(Decimal::from_str("60000.5").unwrap())
+        );
+        assert_eq!(json_decimal(&json, "GBP"), None);
+    }
+}
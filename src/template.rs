@@ -0,0 +1,119 @@
+use crate::price::to_f64;
+use crate::TxDetails;
+use axum::response::Html;
+use bdk_wallet::bitcoin::{Address, FeeRate};
+use bdk_wallet::Balance;
+use rust_decimal::Decimal;
+
+// render the wallet's home page: balance (with an optional fiat estimate),
+// the next receive address, the spend form (with the named confirmation-target
+// fee picker), and the transaction history
+pub(crate) fn home_page(
+    next_unused_address: Address,
+    balance: Balance,
+    balance_fiat: Option<Decimal>,
+    txs: Vec<TxDetails>,
+    fee_targets: Vec<(&'static str, u16, FeeRate)>,
+) -> Html<String> {
+    let balance_fiat = balance_fiat
+        .map(|fiat| format!(" (~${:.2})", to_f64(fiat)))
+        .unwrap_or_default();
+
+    let fee_options = fee_targets
+        .iter()
+        .map(|(name, target, rate)| {
+            format!(
+                r#"<option value="{target}">{name} ({} sat/vB)</option>"#,
+                rate.to_sat_per_vb_ceil()
+            )
+        })
+        .collect::<String>();
+
+    let rows = if txs.is_empty() {
+        r#"<tr><td colspan="8">no transactions yet</td></tr>"#.to_string()
+    } else {
+        txs.iter().map(tx_row).collect::<String>()
+    };
+
+    Html(format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>BDK Workshop Wallet</title>
+</head>
+<body>
+  <h1>Wallet</h1>
+  <p>Balance: {} sats{balance_fiat}</p>
+  <p>Next receive address: <code>{next_unused_address}</code></p>
+
+  <h2>Send</h2>
+  <form method="post" action="/">
+    <label>Address <input type="text" name="address" required></label><br>
+    <label>Amount (sats) <input type="text" name="amount" required></label><br>
+    <label>Confirm within <select name="fee_target">{fee_options}</select></label><br>
+    <label>Note <input type="text" name="note"></label><br>
+    <button type="submit">Send</button>
+  </form>
+
+  <h2>Transactions</h2>
+  <table>
+    <thead>
+      <tr>
+        <th>Txid</th><th>Sent</th><th>Received</th><th>Fee</th>
+        <th>Fee rate</th><th>Fiat</th><th>Note</th><th>Status</th>
+      </tr>
+    </thead>
+    <tbody>
+      {rows}
+    </tbody>
+  </table>
+</body>
+</html>"#,
+        balance.total().to_sat(),
+    ))
+}
+
+fn tx_row(tx: &TxDetails) -> String {
+    let fiat = tx
+        .fiat
+        .map(|fiat| format!("${:.2}", to_f64(fiat)))
+        .unwrap_or_else(|| "-".to_string());
+    let label = tx
+        .label
+        .as_deref()
+        .map(escape_html)
+        .unwrap_or_else(|| "-".to_string());
+    let status = if tx.confirmed() {
+        "confirmed"
+    } else {
+        "unconfirmed"
+    };
+    format!(
+        r#"<tr>
+  <td>{}</td><td>{}</td><td>{}</td><td>{}</td>
+  <td>{} sat/vB</td><td>{fiat}</td><td>{label}</td><td>{status}</td>
+</tr>"#,
+        tx.txid,
+        tx.sent.to_sat(),
+        tx.received.to_sat(),
+        tx.fee.to_sat(),
+        tx.fee_rate.to_sat_per_vb_ceil(),
+    )
+}
+
+// minimal HTML-entity escaping for untrusted text (tx notes/labels) rendered
+// into the page, so a pasted BIP-21 label/message can't inject markup
+fn escape_html(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&#39;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}